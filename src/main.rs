@@ -1,59 +1,179 @@
-use clap::Parser;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
 use nom_exif::{ExifIter, MediaParser, MediaSource, TrackInfo};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 #[derive(Debug, Parser)]
 struct Cli {
-    #[structopt(short = 'i')]
-    input_folder: String,
-    #[structopt(short = 'o')]
-    output_folder: String,
-    #[structopt(short = 'd')]
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    organize: OrganizeArgs,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Reverse a previous run using the journal it wrote with --journal.
+    Undo(UndoArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct UndoArgs {
+    /// Path to the JSON journal written by a previous run's --journal flag.
+    #[arg(long)]
+    journal: String,
+}
+
+#[derive(Debug, clap::Args)]
+struct OrganizeArgs {
+    // Optional at the clap level (despite being required in practice) so that `clap` doesn't
+    // demand them when the user runs the `undo` subcommand instead of organizing files.
+    #[arg(short = 'i')]
+    input_folder: Option<String>,
+    #[arg(short = 'o')]
+    output_folder: Option<String>,
+    #[arg(short = 'd')]
     dry_run: bool,
+    /// Descend into subfolders of the input folder instead of only looking at its top level.
+    #[arg(short = 'r', long)]
+    recursive: bool,
+    /// When recursive, how many levels deep to descend (0 = only the input folder itself).
+    #[arg(long)]
+    max_depth: Option<usize>,
+    /// Cap the number of worker threads used to process files in parallel (defaults to all cores).
+    #[arg(short = 'j', long)]
+    jobs: Option<usize>,
+    /// Destination subfolder template applied to the resolved capture date, e.g. "{year}/{month}"
+    /// or "{year}/{year}-{month}-{day}". Defaults to "{year}".
+    #[arg(long, default_value = "{year}")]
+    pattern: String,
+    /// What to do when the destination file already exists.
+    #[arg(long, default_value = "overwrite")]
+    on_conflict: OnConflict,
+    /// Detect exact-duplicate files (by content hash, scoped per destination subfolder) and
+    /// route repeats to a DUPLICATES/ folder instead of their normal destination.
+    #[arg(long)]
+    dedupe: bool,
+    /// Write a JSON report of the duplicate groups found during this run to this path.
+    #[arg(long)]
+    dedupe_report: Option<String>,
+    /// Comma-separated list of extensions to process, overriding the built-in default set.
+    #[arg(long)]
+    include_ext: Option<String>,
+    /// Comma-separated list of extensions to exclude, applied after --include-ext.
+    #[arg(long)]
+    exclude_ext: Option<String>,
+    /// Write a JSON journal of every {source, destination, date_source, action} record to
+    /// this path, so the run can be inspected or reversed later with `undo --journal`.
+    #[arg(long)]
+    journal: Option<String>,
+}
+
+/// Formats nom_exif (or its exiftool fallback) can plausibly pull a shooting date out of:
+/// common photo/video containers plus HEIC/HEIF and the most common camera RAW formats.
+const DEFAULT_EXTENSIONS: [&str; 11] = [
+    "jpg", "jpeg", "png", "heic", "heif", "mov", "mp4", "cr2", "nef", "arw", "dng",
+];
+
+const PATTERN_TOKENS: [&str; 3] = ["year", "month", "day"];
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum OnConflict {
+    /// Leave the source file untouched and log a warning.
+    Skip,
+    /// Append a numeric suffix (`-1`, `-2`, ...) to the incoming file's name.
+    Rename,
+    /// Replace the existing file at the destination (previous behavior).
+    Overwrite,
 }
 
 struct Logger {
     is_debug: bool,
+    // Guards stdout/stderr so progress lines from concurrent workers don't interleave.
+    print_lock: Mutex<()>,
 }
 
 impl Logger {
     fn new() -> Self {
         let is_debug =
-            env::var("DEBUG").map_or(false, |env_value| env_value.to_lowercase() == "true");
+            env::var("DEBUG").is_ok_and(|env_value| env_value.to_lowercase() == "true");
         println!("Debug mode: {is_debug}");
 
-        Self { is_debug }
+        Self {
+            is_debug,
+            print_lock: Mutex::new(()),
+        }
     }
 
     fn info(&self, message: String) {
+        let _guard = self.print_lock.lock().unwrap();
         println!("ℹ️ INFO: {}", message);
     }
 
     fn warning(&self, message: String) {
+        let _guard = self.print_lock.lock().unwrap();
         println!("⛔️ WARNING: {}", message);
     }
 
     fn debug(&self, message: String) {
         if self.is_debug {
+            let _guard = self.print_lock.lock().unwrap();
             println!("🪲 DEBUG: {}", message);
         }
     }
 
     fn error(&self, message: String) {
+        let _guard = self.print_lock.lock().unwrap();
         eprintln!("💣 ERROR: {}.", message);
     }
 }
 
 fn main() {
     let logger = Logger::new();
-    let args = Cli::parse();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Undo(undo_args)) => run_undo(&logger, &undo_args),
+        None => run_organize(&logger, cli.organize),
+    }
+}
+
+fn run_organize(logger: &Logger, args: OrganizeArgs) {
+    let Some(input_folder) = &args.input_folder else {
+        logger.error("-i <input folder> is required".to_owned());
+        process::exit(1);
+    };
+    let Some(output_folder) = &args.output_folder else {
+        logger.error("-o <output folder> is required".to_owned());
+        process::exit(1);
+    };
+
+    if let Err(error) = validate_pattern(&args.pattern) {
+        logger.error(format!(
+            "Invalid --pattern {:?}. Details: {error}",
+            args.pattern
+        ));
+        process::exit(1);
+    }
 
     logger.debug("Reading input folder...📖".to_owned());
 
-    let folder = fs::read_dir(&args.input_folder).unwrap_or_else(|error| {
+    let input_root = Path::new(input_folder);
+    let max_depth = if args.recursive {
+        args.max_depth
+    } else {
+        Some(0)
+    };
+
+    let folder_collection = collect_files(input_root, max_depth).unwrap_or_else(|error| {
         logger.error(format!(
             "Error while reading input directory. Details: {error}"
         ));
@@ -63,35 +183,89 @@ fn main() {
 
     logger.info("Iterating through files in the folder...🏃".to_owned());
 
-    let folder_collection: Vec<_> = folder.collect();
+    let accepted_extensions = resolve_accepted_extensions(&args);
+
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .unwrap_or_else(|error| {
+                logger.error(format!(
+                    "Failed to configure the worker thread pool. Details: {error}"
+                ));
+
+                process::exit(1);
+            });
+    }
+
     let amount_of_files = folder_collection.len();
+    let processed_count = AtomicUsize::new(0);
 
-    for (index, file) in folder_collection.into_iter().enumerate() {
-        let path = file.unwrap().path();
-        let file_name = path.file_name().unwrap().to_str().unwrap();
+    // Capture date resolution and (optionally) dedupe hashing run freely across the worker
+    // pool; `.collect()` on a rayon iterator preserves the source order regardless of which
+    // worker happened to finish first, so `decisions` ends up in the same order every run.
+    let mut decisions: Vec<FileDecision> = folder_collection
+        .into_par_iter()
+        .filter_map(|path| {
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            let index = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
 
-        logger.info(format!(
-            "Processing file {index} of {amount_of_files} ⏳",
-            index = index + 1
-        ));
-        logger.debug(format!("File name: {file_name} 🪲"));
+            logger.info(format!("Processing file {index} of {amount_of_files} ⏳"));
+            logger.debug(format!("File name: {file_name} 🪲"));
 
-        let extension = path.extension().unwrap().to_str().unwrap().to_lowercase();
+            let Some(extension) = path.extension().and_then(|extension| extension.to_str())
+            else {
+                logger.debug(format!("{path:?} has no extension, skipping it."));
+                return None;
+            };
+            let extension = extension.to_lowercase();
 
-        if !["jpg", "jpeg", "png", "mov", "mp4"].contains(&extension.as_str()) {
-            continue;
-        }
+            if !accepted_extensions.contains(&extension) {
+                return None;
+            }
 
-        let destination_subfolder_name =
-            match determine_subfolder_name_from_metadata(&logger, path.clone()) {
-                Some(value) => value,
-                None => continue,
+            let (capture_date, date_source) = resolve_capture_date(logger, &path);
+            let destination_subfolder_name = match capture_date {
+                Some(date) => render_pattern(&args.pattern, date),
+                None => "NOT_QUALIFIED".to_owned(),
             };
 
+            let dedupe_hash = if args.dedupe {
+                hash_for_dedupe(logger, &path)
+            } else {
+                None
+            };
+
+            Some(FileDecision {
+                path,
+                date_source,
+                destination_subfolder_name,
+                dedupe_hash,
+            })
+        })
+        .collect();
+
+    let duplicate_groups = if args.dedupe {
+        apply_dedupe_overrides(logger, &mut decisions)
+    } else {
+        Vec::new()
+    };
+
+    if let Some(report_path) = &args.dedupe_report {
+        write_dedupe_report(logger, &duplicate_groups, report_path);
+    }
+
+    // Moves touch the filesystem (create_dir_all + rename), so they're serialized while
+    // everything above ran freely across the worker pool.
+    let move_lock = Mutex::new(());
+    let journal_tracker = JournalTracker::new();
+
+    decisions.into_par_iter().for_each(|decision| {
+        let file_name = decision.path.file_name().unwrap().to_str().unwrap();
         let new_file_path_str = format!(
             "{output_folder}/{destination_subfolder_name}/{file_name}",
-            output_folder = args.output_folder,
-            destination_subfolder_name = destination_subfolder_name,
+            output_folder = output_folder,
+            destination_subfolder_name = decision.destination_subfolder_name,
             file_name = file_name,
         );
         let new_file_path = Path::new(&new_file_path_str);
@@ -103,24 +277,453 @@ fn main() {
                 file_name = file_name,
                 new_file_path = new_file_path_str
             ));
+            journal_tracker.record(JournalEntry {
+                source: decision.path.clone(),
+                destination: new_file_path.to_path_buf(),
+                date_source: decision.date_source,
+                action: JournalAction::DryRun,
+            });
         } else {
+            let _guard = move_lock.lock().unwrap();
             fs::create_dir_all(new_file_path.parent().unwrap()).unwrap();
-            fs::rename(path.clone(), new_file_path).unwrap();
+
+            let (actual_destination, action) =
+                match move_file(logger, &decision.path, new_file_path, args.on_conflict) {
+                    Some(actual_destination) => (actual_destination, JournalAction::Moved),
+                    None => (new_file_path.to_path_buf(), JournalAction::Skipped),
+                };
+
+            journal_tracker.record(JournalEntry {
+                source: decision.path.clone(),
+                destination: actual_destination,
+                date_source: decision.date_source,
+                action,
+            });
+        }
+    });
+
+    if let Some(journal_path) = &args.journal {
+        journal_tracker.write(logger, journal_path);
+    }
+}
+
+/// Builds the effective set of file extensions to process: `--include-ext` replaces
+/// [`DEFAULT_EXTENSIONS`] when given, then `--exclude-ext` removes any that should be
+/// skipped regardless.
+fn resolve_accepted_extensions(args: &OrganizeArgs) -> Vec<String> {
+    let mut extensions = match &args.include_ext {
+        Some(list) => split_ext_list(list),
+        None => DEFAULT_EXTENSIONS
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+    };
+
+    if let Some(exclude) = &args.exclude_ext {
+        let excluded = split_ext_list(exclude);
+        extensions.retain(|ext| !excluded.contains(ext));
+    }
+
+    extensions
+}
+
+/// Splits a comma-separated `--include-ext`/`--exclude-ext` value into lowercase, dot-free
+/// extensions, e.g. `"JPG, .heic"` -> `["jpg", "heic"]`.
+fn split_ext_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+/// Walks `root` and returns every file found, optionally descending into subfolders.
+///
+/// `max_depth` limits how many levels below `root` are visited (`Some(0)` means only
+/// `root` itself); `None` means descend without limit.
+fn collect_files(root: &Path, max_depth: Option<usize>) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut stack = vec![(root.to_path_buf(), 0usize)];
+
+    while let Some((dir, depth)) = stack.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                let within_depth = match max_depth {
+                    Some(limit) => depth < limit,
+                    None => true,
+                };
+
+                if within_depth {
+                    stack.push((path, depth + 1));
+                }
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// What happened to a file during an organize run; recorded per-file in the move journal.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalAction {
+    /// The file was actually moved to `destination`.
+    Moved,
+    /// `--dry-run` was set, so `destination` is only where the file would have gone.
+    DryRun,
+    /// The file was left at `source`, either because of the `--on-conflict skip` policy or
+    /// because the move failed; `destination` is where it would have gone.
+    Skipped,
+}
+
+/// One row of the move journal written by `--journal` and read back by `undo --journal`.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    source: PathBuf,
+    destination: PathBuf,
+    date_source: DateSource,
+    action: JournalAction,
+}
+
+/// Collects journal entries from every worker thread so they can be written out in one go
+/// once the whole run finishes.
+struct JournalTracker {
+    entries: Mutex<Vec<JournalEntry>>,
+}
+
+impl JournalTracker {
+    fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn record(&self, entry: JournalEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// Writes every recorded entry to `journal_path` as JSON.
+    fn write(&self, logger: &Logger, journal_path: &str) {
+        let entries = self.entries.lock().unwrap();
+
+        let json = match serde_json::to_string_pretty(&*entries) {
+            Ok(json) => json,
+            Err(error) => {
+                logger.error(format!(
+                    "Failed serializing the move journal. Details: {error}"
+                ));
+                return;
+            }
+        };
+
+        if let Err(error) = fs::write(journal_path, json) {
+            logger.error(format!(
+                "Failed writing the move journal to {journal_path}. Details: {error}"
+            ));
         }
     }
 }
 
-fn determine_subfolder_name_from_metadata(
+/// Reverses a previous run by reading its `--journal` file and moving every `Moved` entry's
+/// `destination` back to its original `source`. `DryRun`/`Skipped` entries never touched the
+/// filesystem, so they're left alone.
+fn run_undo(logger: &Logger, undo_args: &UndoArgs) {
+    let journal_contents = fs::read_to_string(&undo_args.journal).unwrap_or_else(|error| {
+        logger.error(format!(
+            "Couldn't read journal {}. Details: {error}",
+            undo_args.journal
+        ));
+        process::exit(1);
+    });
+
+    let entries: Vec<JournalEntry> =
+        serde_json::from_str(&journal_contents).unwrap_or_else(|error| {
+            logger.error(format!(
+                "Couldn't parse journal {}. Details: {error}",
+                undo_args.journal
+            ));
+            process::exit(1);
+        });
+
+    for entry in entries {
+        if !matches!(entry.action, JournalAction::Moved) {
+            continue;
+        }
+
+        if let Some(parent) = entry.source.parent() {
+            if let Err(error) = fs::create_dir_all(parent) {
+                logger.error(format!(
+                    "Couldn't recreate {parent:?} to undo {:?}. Details: {error}",
+                    entry.destination
+                ));
+                continue;
+            }
+        }
+
+        match rename_or_copy(logger, &entry.destination, &entry.source) {
+            Ok(()) => logger.info(format!(
+                "Restored {:?} to {:?}",
+                entry.destination, entry.source
+            )),
+            Err(error) => logger.error(format!(
+                "Failed to undo move of {:?} back to {:?}. Details: {error}",
+                entry.destination, entry.source
+            )),
+        }
+    }
+}
+
+/// What an organize run decided to do with one file, after resolving its capture date (and,
+/// with `--dedupe`, its content hash) but before anything has been moved.
+struct FileDecision {
+    path: PathBuf,
+    date_source: DateSource,
+    destination_subfolder_name: String,
+    dedupe_hash: Option<blake3::Hash>,
+}
+
+/// Hashes `path`'s full contents for `--dedupe`, logging and returning `None` if it can't be
+/// read (in which case the file is left out of dedupe consideration entirely).
+fn hash_for_dedupe(logger: &Logger, path: &Path) -> Option<blake3::Hash> {
+    match fs::read(path) {
+        Ok(bytes) => Some(blake3::hash(&bytes)),
+        Err(error) => {
+            logger.warning(format!(
+                "Couldn't hash {path:?} for dedupe, skipping dedupe check. Details: {error}"
+            ));
+            None
+        }
+    }
+}
+
+/// Decides, per `(destination subfolder, content hash)` group, which file keeps that
+/// destination and which are routed to `DUPLICATES/` instead, then returns every group that
+/// had more than one member.
+///
+/// Groups are built by walking `decisions` in path-sorted order, so the keeper is always the
+/// lexicographically smallest path in its group — independent of the order the parallel
+/// hashing pass above happened to finish in, which is what makes reruns on the same input
+/// deterministic.
+fn apply_dedupe_overrides(logger: &Logger, decisions: &mut [FileDecision]) -> Vec<Vec<PathBuf>> {
+    let mut order: Vec<usize> = (0..decisions.len()).collect();
+    order.sort_by(|&a, &b| decisions[a].path.cmp(&decisions[b].path));
+
+    let mut groups: HashMap<(String, blake3::Hash), Vec<usize>> = HashMap::new();
+    for index in order {
+        let Some(hash) = decisions[index].dedupe_hash else {
+            continue;
+        };
+        let key = (decisions[index].destination_subfolder_name.clone(), hash);
+        groups.entry(key).or_default().push(index);
+    }
+
+    let mut duplicate_groups: Vec<Vec<PathBuf>> = Vec::new();
+
+    for indices in groups.into_values() {
+        if indices.len() < 2 {
+            continue;
+        }
+
+        let keeper = indices[0];
+        duplicate_groups.push(indices.iter().map(|&index| decisions[index].path.clone()).collect());
+
+        for &index in &indices[1..] {
+            logger.warning(format!(
+                "{:?} is a duplicate of {:?}, routing to DUPLICATES/.",
+                decisions[index].path, decisions[keeper].path
+            ));
+            decisions[index].destination_subfolder_name = "DUPLICATES".to_owned();
+        }
+    }
+
+    duplicate_groups.sort_by(|a, b| a[0].cmp(&b[0]));
+    duplicate_groups
+}
+
+/// Writes the duplicate groups found this run to `report_path` as JSON.
+fn write_dedupe_report(logger: &Logger, groups: &[Vec<PathBuf>], report_path: &str) {
+    let json = match serde_json::to_string_pretty(groups) {
+        Ok(json) => json,
+        Err(error) => {
+            logger.error(format!(
+                "Failed serializing the dedupe report. Details: {error}"
+            ));
+            return;
+        }
+    };
+
+    if let Err(error) = fs::write(report_path, json) {
+        logger.error(format!(
+            "Failed writing the dedupe report to {report_path}. Details: {error}"
+        ));
+    }
+}
+
+/// Moves `source` to `destination`, applying `on_conflict` if a file is already there.
+/// Returns the actual path the file ended up at, or `None` if it was skipped or the move
+/// failed (the failure is logged).
+fn move_file(
     logger: &Logger,
-    path: std::path::PathBuf,
-) -> Option<String> {
-    let mut destination_subfolder_name = "NOT_QUALIFIED".to_owned();
+    source: &Path,
+    destination: &Path,
+    on_conflict: OnConflict,
+) -> Option<PathBuf> {
+    let destination = resolve_conflict(logger, destination, on_conflict)?;
+
+    if let Err(error) = rename_or_copy(logger, source, &destination) {
+        logger.error(format!(
+            "Failed to move {source:?} to {destination:?}. Details: {error}"
+        ));
+        return None;
+    }
+
+    Some(destination)
+}
+
+/// Decides the real destination path for a move given the conflict policy. Returns `None`
+/// when the file should be skipped entirely.
+fn resolve_conflict(
+    logger: &Logger,
+    destination: &Path,
+    on_conflict: OnConflict,
+) -> Option<PathBuf> {
+    if !destination.exists() {
+        return Some(destination.to_path_buf());
+    }
+
+    match on_conflict {
+        OnConflict::Overwrite => Some(destination.to_path_buf()),
+        OnConflict::Skip => {
+            logger.warning(format!(
+                "{destination:?} already exists, skipping it (--on-conflict skip)."
+            ));
+            None
+        }
+        OnConflict::Rename => Some(next_available_path(destination)),
+    }
+}
+
+/// Appends `-1`, `-2`, ... to `path`'s file stem until a name that doesn't exist yet is found.
+fn next_available_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("file");
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut suffix = 1;
+    loop {
+        let candidate_name = match extension {
+            Some(extension) => format!("{stem}-{suffix}.{extension}"),
+            None => format!("{stem}-{suffix}"),
+        };
+        let candidate = parent.join(candidate_name);
+
+        if !candidate.exists() {
+            return candidate;
+        }
+
+        suffix += 1;
+    }
+}
+
+/// Renames `source` to `destination`, falling back to copy-then-delete when `rename` fails
+/// (e.g. `EXDEV` when the two paths live on different filesystems).
+fn rename_or_copy(logger: &Logger, source: &Path, destination: &Path) -> std::io::Result<()> {
+    if let Err(error) = fs::rename(source, destination) {
+        logger.debug(format!(
+            "rename failed ({error}), falling back to copy+delete for {source:?} -> {destination:?}"
+        ));
+        fs::copy(source, destination)?;
+        fs::remove_file(source)?;
+    }
+
+    Ok(())
+}
+
+/// Checks that every `{token}` placeholder in `pattern` is one of [`PATTERN_TOKENS`], so a
+/// typo'd `--pattern` is rejected before any files are moved rather than producing a
+/// literal `{typo}` folder name.
+fn validate_pattern(pattern: &str) -> Result<(), String> {
+    let mut remainder = pattern;
+
+    while let Some(open) = remainder.find('{') {
+        let close = remainder[open..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed '{{' in pattern {pattern:?}"))?;
+        let token = &remainder[open + 1..open + close];
+
+        if !PATTERN_TOKENS.contains(&token) {
+            return Err(format!(
+                "unknown placeholder {{{token}}}, expected one of {PATTERN_TOKENS:?}"
+            ));
+        }
+
+        remainder = &remainder[open + close + 1..];
+    }
+
+    Ok(())
+}
+
+/// Substitutes the `{year}`/`{month}`/`{day}` placeholders in `pattern` with the fields of
+/// `date`. Assumes `pattern` already passed [`validate_pattern`].
+fn render_pattern(pattern: &str, date: NaiveDate) -> String {
+    pattern
+        .replace("{year}", &date.format("%Y").to_string())
+        .replace("{month}", &date.format("%m").to_string())
+        .replace("{day}", &date.format("%d").to_string())
+}
+
+/// Which tier resolved a file's capture date, recorded in the move journal so a run can be
+/// audited after the fact.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DateSource {
+    NomExif,
+    Exiftool,
+    Filesystem,
+    Unresolved,
+}
+
+/// Resolves the capture date of the file at `path`, trying each source in turn until one
+/// yields a date: embedded Exif/track metadata first, then the `exiftool` binary (useful for
+/// container formats nom_exif can't fully parse), then the filesystem's own timestamps.
+fn resolve_capture_date(logger: &Logger, path: &Path) -> (Option<NaiveDate>, DateSource) {
+    if let Some(date) = resolve_capture_date_from_nom_exif(logger, path) {
+        logger.debug(format!("Resolved {path:?} via nom_exif"));
+        return (Some(date), DateSource::NomExif);
+    }
+
+    if let Some(date) = resolve_capture_date_from_exiftool(logger, path) {
+        logger.debug(format!("Resolved {path:?} via exiftool fallback"));
+        return (Some(date), DateSource::Exiftool);
+    }
+
+    if let Some(date) = resolve_capture_date_from_filesystem(logger, path) {
+        logger.debug(format!(
+            "Resolved {path:?} via filesystem timestamp fallback"
+        ));
+        return (Some(date), DateSource::Filesystem);
+    }
+
+    logger.warning(format!(
+        "Couldn't resolve a capture date for {path:?} from any source."
+    ));
+
+    (None, DateSource::Unresolved)
+}
+
+fn resolve_capture_date_from_nom_exif(logger: &Logger, path: &Path) -> Option<NaiveDate> {
     let mut parser = MediaParser::new();
     let media_source = match MediaSource::file_path(path) {
         Ok(source) => source,
         Err(error) => {
             logger.warning(format!(
-                "Couldn't get metadata of the file so skipping it. Details: {error}"
+                "Couldn't get metadata of the file. Details: {error}"
             ));
             return None;
         }
@@ -131,8 +734,7 @@ fn determine_subfolder_name_from_metadata(
             Ok(iter) => iter,
             Err(error) => {
                 logger.warning(format!("Failed parsing Exif data. Details: {error}"));
-
-                return Some(destination_subfolder_name);
+                return None;
             }
         };
 
@@ -141,40 +743,294 @@ fn determine_subfolder_name_from_metadata(
                 entry.tag(),
                 Some(nom_exif::ExifTag::DateTimeOriginal | nom_exif::ExifTag::CreateDate)
             )
-        });
-        let exif_entry = match exif_entry {
-            Some(entry) => entry,
-            None => {
-                logger.warning(
-                    "Failed reading Exif data. Details: No DateTimeOriginal or CreateDate tag found.".to_owned(),
-                );
-
-                return Some(destination_subfolder_name);
-            }
-        };
-        let exif_data = exif_entry.get_value().unwrap().as_time();
+        })?;
 
-        destination_subfolder_name = exif_data.unwrap().date_naive().format("%Y").to_string();
+        exif_entry
+            .get_value()?
+            .as_time_components()
+            .map(|(naive_date_time, _offset)| naive_date_time.date())
     } else if media_source.has_track() {
         let info: TrackInfo = match parser.parse(media_source) {
             Ok(info) => info,
             Err(error) => {
                 logger.warning(format!("Failed parsing track data. Details: {error}"));
-
-                return Some(destination_subfolder_name);
+                return None;
             }
         };
-        let track_data = info.get(nom_exif::TrackInfoTag::CreateDate).unwrap();
-
-        destination_subfolder_name = track_data
-            .as_time()
-            .unwrap()
-            .date_naive()
-            .format("%Y")
-            .to_string();
+
+        info.get(nom_exif::TrackInfoTag::CreateDate)?
+            .as_time_components()
+            .map(|(naive_date_time, _offset)| naive_date_time.date())
     } else {
-        logger.warning("No Exif or Track data found so skipping the current file.".to_owned());
+        logger.debug("No Exif or Track data found in this file.".to_owned());
+        None
+    }
+}
+
+/// Shells out to `exiftool -j` and looks for a date tag in its JSON output. Returns `None`
+/// (rather than erroring) when the binary isn't installed, since this is only a fallback.
+fn resolve_capture_date_from_exiftool(logger: &Logger, path: &Path) -> Option<NaiveDate> {
+    let output = match process::Command::new("exiftool")
+        .arg("-j")
+        .arg(path)
+        .output()
+    {
+        Ok(output) => output,
+        Err(error) => {
+            logger.debug(format!("exiftool unavailable or failed to run: {error}"));
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        logger.debug(format!(
+            "exiftool exited with a non-zero status for {path:?}"
+        ));
+        return None;
+    }
+
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let entry = entries.as_array()?.first()?;
+
+    for tag in ["DateTimeOriginal", "CreateDate", "MediaCreateDate"] {
+        let Some(raw_value) = entry.get(tag).and_then(|value| value.as_str()) else {
+            continue;
+        };
+
+        if let Some(date) = parse_exiftool_date(raw_value) {
+            return Some(date);
+        }
     }
 
-    Some(destination_subfolder_name)
+    None
+}
+
+/// exiftool's default `-j` output formats dates as `YYYY:MM:DD HH:MM:SS`.
+fn parse_exiftool_date(raw_value: &str) -> Option<NaiveDate> {
+    let date_part = raw_value.split(' ').next()?;
+    NaiveDate::parse_from_str(date_part, "%Y:%m:%d").ok()
+}
+
+/// Last-resort fallback: the file's own creation time, or modification time if creation
+/// time isn't available on this platform/filesystem.
+fn resolve_capture_date_from_filesystem(logger: &Logger, path: &Path) -> Option<NaiveDate> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) => {
+            logger.warning(format!(
+                "Couldn't read filesystem metadata for {path:?}. Details: {error}"
+            ));
+            return None;
+        }
+    };
+
+    let system_time = metadata.created().or_else(|_| metadata.modified()).ok()?;
+    let datetime: chrono::DateTime<chrono::Local> = system_time.into();
+
+    Some(datetime.date_naive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn organize_args() -> OrganizeArgs {
+        OrganizeArgs {
+            input_folder: None,
+            output_folder: None,
+            dry_run: false,
+            recursive: false,
+            max_depth: None,
+            jobs: None,
+            pattern: "{year}".to_owned(),
+            on_conflict: OnConflict::Overwrite,
+            dedupe: false,
+            dedupe_report: None,
+            include_ext: None,
+            exclude_ext: None,
+            journal: None,
+        }
+    }
+
+    #[test]
+    fn validate_pattern_accepts_known_tokens() {
+        assert!(validate_pattern("{year}/{month}-{day}").is_ok());
+        assert!(validate_pattern("no-tokens-at-all").is_ok());
+    }
+
+    #[test]
+    fn validate_pattern_rejects_unknown_token() {
+        assert!(validate_pattern("{yeah}").is_err());
+    }
+
+    #[test]
+    fn validate_pattern_rejects_unclosed_brace() {
+        assert!(validate_pattern("{year").is_err());
+    }
+
+    #[test]
+    fn render_pattern_substitutes_all_tokens() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 7).unwrap();
+        assert_eq!(render_pattern("{year}/{month}/{day}", date), "2024/03/07");
+    }
+
+    #[test]
+    fn render_pattern_leaves_unknown_placeholders_untouched() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 7).unwrap();
+        assert_eq!(render_pattern("{year}-{typo}", date), "2024-{typo}");
+    }
+
+    #[test]
+    fn apply_dedupe_overrides_keeps_lexicographically_smallest_path() {
+        let logger = Logger {
+            is_debug: false,
+            print_lock: Mutex::new(()),
+        };
+
+        let hash_a = blake3::hash(b"same bytes");
+        let hash_b = blake3::hash(b"different bytes");
+
+        let decision = |path: &str, hash: blake3::Hash| FileDecision {
+            path: PathBuf::from(path),
+            date_source: DateSource::Unresolved,
+            destination_subfolder_name: "2024".to_owned(),
+            dedupe_hash: Some(hash),
+        };
+
+        // Inserted out of sorted order, so a correct fix can't rely on insertion order.
+        let mut decisions = vec![
+            decision("c.jpg", hash_a),
+            decision("a.jpg", hash_a),
+            decision("b.jpg", hash_a),
+            decision("only.jpg", hash_b),
+        ];
+
+        let duplicate_groups = apply_dedupe_overrides(&logger, &mut decisions);
+
+        assert_eq!(duplicate_groups.len(), 1);
+        assert_eq!(
+            duplicate_groups[0],
+            vec![
+                PathBuf::from("a.jpg"),
+                PathBuf::from("b.jpg"),
+                PathBuf::from("c.jpg"),
+            ]
+        );
+
+        let by_path = |path: &str| {
+            decisions
+                .iter()
+                .find(|decision| decision.path == Path::new(path))
+                .unwrap()
+        };
+
+        assert_eq!(by_path("a.jpg").destination_subfolder_name, "2024");
+        assert_eq!(by_path("b.jpg").destination_subfolder_name, "DUPLICATES");
+        assert_eq!(by_path("c.jpg").destination_subfolder_name, "DUPLICATES");
+        assert_eq!(by_path("only.jpg").destination_subfolder_name, "2024");
+    }
+
+    #[test]
+    fn split_ext_list_trims_dots_and_whitespace() {
+        assert_eq!(
+            split_ext_list(" JPG, .heic ,,png"),
+            vec!["jpg".to_owned(), "heic".to_owned(), "png".to_owned()]
+        );
+    }
+
+    #[test]
+    fn resolve_accepted_extensions_defaults_when_no_include() {
+        let args = organize_args();
+        let extensions = resolve_accepted_extensions(&args);
+        assert!(extensions.contains(&"jpg".to_owned()));
+        assert!(extensions.contains(&"dng".to_owned()));
+    }
+
+    #[test]
+    fn resolve_accepted_extensions_honors_include_and_exclude() {
+        let mut args = organize_args();
+        args.include_ext = Some("jpg,png,heic".to_owned());
+        args.exclude_ext = Some("png".to_owned());
+
+        assert_eq!(
+            resolve_accepted_extensions(&args),
+            vec!["jpg".to_owned(), "heic".to_owned()]
+        );
+    }
+
+    #[test]
+    fn next_available_path_appends_suffix_until_free() {
+        let dir = std::env::temp_dir().join(format!(
+            "media-organizer-test-{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("photo.jpg");
+        fs::write(&base, b"a").unwrap();
+        fs::write(dir.join("photo-1.jpg"), b"b").unwrap();
+
+        assert_eq!(next_available_path(&base), dir.join("photo-2.jpg"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolve_conflict_skip_returns_none_when_destination_exists() {
+        let dir = std::env::temp_dir().join(format!(
+            "media-organizer-test-skip-{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let destination = dir.join("photo.jpg");
+        fs::write(&destination, b"a").unwrap();
+
+        let logger = Logger {
+            is_debug: false,
+            print_lock: Mutex::new(()),
+        };
+
+        assert!(resolve_conflict(&logger, &destination, OnConflict::Skip).is_none());
+        assert_eq!(
+            resolve_conflict(&logger, &destination, OnConflict::Overwrite),
+            Some(destination.clone())
+        );
+        assert_eq!(
+            resolve_conflict(&logger, &destination, OnConflict::Rename),
+            Some(dir.join("photo-1.jpg"))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolve_conflict_returns_destination_when_free() {
+        let dir = std::env::temp_dir().join(format!(
+            "media-organizer-test-free-{:?}",
+            std::thread::current().id()
+        ));
+        let destination = dir.join("photo.jpg");
+
+        let logger = Logger {
+            is_debug: false,
+            print_lock: Mutex::new(()),
+        };
+
+        assert_eq!(
+            resolve_conflict(&logger, &destination, OnConflict::Skip),
+            Some(destination)
+        );
+    }
+
+    #[test]
+    fn parse_exiftool_date_parses_colon_separated_date() {
+        assert_eq!(
+            parse_exiftool_date("2023:11:02 08:15:30"),
+            NaiveDate::from_ymd_opt(2023, 11, 2)
+        );
+    }
+
+    #[test]
+    fn parse_exiftool_date_rejects_garbage() {
+        assert_eq!(parse_exiftool_date("not-a-date"), None);
+    }
 }